@@ -12,4 +12,9 @@ pub enum WordPiecesError {
     /// IO error.
     #[error(transparent)]
     IOError(#[from] io::Error),
+
+    /// The serialized word pieces data is malformed or was written by
+    /// an incompatible version of this crate.
+    #[error("invalid word pieces format: {0}")]
+    InvalidFormat(String),
 }
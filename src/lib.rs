@@ -9,23 +9,22 @@
 //! The piece is represented as a string and its vocabulary index.
 //!
 //! ~~~
-//! use std::convert::TryFrom;
 //! use std::fs::File;
 //! use std::io::{BufRead, BufReader};
 //!
 //! use wordpieces::{WordPiece, WordPieces};
 //!
 //! let f = File::open("testdata/test.pieces").unwrap();
-//! let word_pieces = WordPieces::try_from(BufReader::new(f).lines()).unwrap();
+//! let word_pieces = WordPieces::from_buf_read(BufReader::new(f)).unwrap();
 //!
 //! // A word that can be split fully.
-//! let pieces = word_pieces.split("coördinatie")
-//!  .map(|p| p.piece()).collect::<Vec<_>>();
+//! let pieces = word_pieces.split("coördinatie").collect::<Vec<_>>();
+//! let pieces = pieces.iter().map(|p| p.piece()).collect::<Vec<_>>();
 //! assert_eq!(pieces, vec![Some("coördina"), Some("tie")]);
 //!
 //! // A word that can be split partially.
-//! let pieces = word_pieces.split("voorkomen")
-//!  .map(|p| p.piece()).collect::<Vec<_>>();
+//! let pieces = word_pieces.split("voorbaz").collect::<Vec<_>>();
+//! let pieces = pieces.iter().map(|p| p.piece()).collect::<Vec<_>>();
 //! assert_eq!(pieces, vec![Some("voor"), None]);
 //! ~~~
 
@@ -36,4 +35,4 @@ mod error;
 pub use error::WordPiecesError;
 
 mod word_pieces;
-pub use word_pieces::{WordPiece, WordPieces};
+pub use word_pieces::{CompletionInfo, SplitConfig, Token, WordPiece, WordPieces};
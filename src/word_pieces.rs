@@ -1,11 +1,24 @@
 use std::collections::BTreeMap;
-use std::io::BufRead;
+use std::convert::TryInto;
+use std::io::{BufRead, Write};
+use std::ops::Range;
 
-use fst::raw::Output;
-use fst::{self, Map, MapBuilder, Streamer};
+use fst::automaton::{Levenshtein, Str};
+use fst::raw::{Fst, Node, Output};
+use fst::{self, Automaton, IntoStreamer, Map, MapBuilder, Streamer};
 
 use crate::WordPiecesError;
 
+/// Magic bytes identifying a serialized [`WordPieces`] vocabulary, as
+/// written by [`WordPieces::write`].
+const MAGIC: &[u8; 4] = b"WPCS";
+
+/// The version of the binary format written by [`WordPieces::write`].
+///
+/// This is bumped whenever the layout written by `write` changes in a
+/// way that is not backwards compatible with `from_bytes`/`from_mmap`.
+const FORMAT_VERSION: u32 = 1;
+
 pub struct WordPiecesBuilder {
     word_initial: BTreeMap<String, u64>,
     continuation: BTreeMap<String, u64>,
@@ -47,39 +60,36 @@ impl WordPiecesBuilder {
 }
 
 /// A set of word pieces.
-pub struct WordPieces {
-    word_initial: Map<Vec<u8>>,
-    continuation: Map<Vec<u8>>,
+///
+/// `WordPieces` is generic over the backing storage `D` of its two
+/// `fst::Map`s, so that it can either own its data (`D = Vec<u8>`, the
+/// default, as built by [`WordPiecesBuilder`] and
+/// [`from_buf_read`](WordPieces::from_buf_read)) or borrow it
+/// zero-copy from a byte slice or memory-mapped file (as constructed
+/// by [`from_bytes`](WordPieces::from_bytes) or
+/// [`from_mmap`](WordPieces::from_mmap)).
+pub struct WordPieces<D = Vec<u8>> {
+    word_initial: Map<D>,
+    continuation: Map<D>,
 }
 
-impl WordPieces {
+impl<D> WordPieces<D>
+where
+    D: AsRef<[u8]>,
+{
     /// Construct new word pieces instance.
     ///
     /// The arguments are set of word-initial pieces and the set o
     /// continuation pieces. The continuation set pieces must not
     /// have continuation markers (such as `##`).
-    pub fn new(word_initial: Map<Vec<u8>>, continuation: Map<Vec<u8>>) -> Self {
+    pub fn new(word_initial: Map<D>, continuation: Map<D>) -> Self {
         WordPieces {
             word_initial,
             continuation,
         }
     }
 
-    pub fn from_buf_read(buf_read: impl BufRead) -> Result<Self, WordPiecesError> {
-        let mut builder = WordPiecesBuilder::new();
-
-        for (idx, piece) in buf_read.lines().enumerate() {
-            let piece = piece?;
-            builder.insert(&piece, idx as u64);
-        }
-
-        builder.build()
-    }
-
-    fn longest_prefix_len<D>(piece_map: &Map<D>, word: &str) -> (usize, u64)
-    where
-        D: AsRef<[u8]>,
-    {
+    fn longest_prefix_len(piece_map: &Map<D>, word: &str) -> (usize, u64) {
         let fst = piece_map.as_fst();
 
         let mut node = fst.root();
@@ -122,17 +132,620 @@ impl WordPieces {
     /// Split a string into word pieces.
     ///
     /// Returns an iterator over the word pieces.
-    pub fn split<'a, 'b>(&'a self, word: &'b str) -> WordPieceIter<'a, 'b> {
+    pub fn split<'a, 'b>(&'a self, word: &'b str) -> WordPieceIter<'a, 'b, D> {
         WordPieceIter {
             word_pieces: self,
             word,
             initial: true,
         }
     }
+
+    /// Split a string into word pieces, tolerating small typos.
+    ///
+    /// Works like [`split`](WordPieces::split), but when the greedy
+    /// longest-match search finds no exact piece for the remainder
+    /// of the word, it looks for the closest piece within
+    /// `max_distance` edits, using a Levenshtein automaton
+    /// intersected with the word-initial or continuation piece set,
+    /// rather than giving up on the rest of the word.
+    ///
+    /// `fst`'s Levenshtein automaton has a combined limit on
+    /// `max_distance` and the length of the text it is matched
+    /// against, rather than a flat cap on `max_distance` alone. If
+    /// that limit is exceeded for a given prefix of `word` -- which
+    /// can happen for long inputs even with a small `max_distance` --
+    /// that prefix length is skipped rather than attempted, falling
+    /// back to [`WordPiece::Missing`] if no prefix length yields a
+    /// usable automaton.
+    pub fn split_fuzzy<'a, 'b>(
+        &'a self,
+        word: &'b str,
+        max_distance: u32,
+    ) -> WordPieceFuzzyIter<'a, 'b, D> {
+        WordPieceFuzzyIter {
+            word_pieces: self,
+            word,
+            initial: true,
+            max_distance,
+        }
+    }
+
+    /// Split a string into word pieces, following a [`SplitConfig`].
+    ///
+    /// Unlike [`split`](WordPieces::split), which always emits the
+    /// pieces it managed to find followed by a single `Missing` for
+    /// whatever is left over, `split_with` can be configured to
+    /// match the original BERT WordPiece algorithm: a word longer
+    /// than `max_input_chars_per_word` is rejected outright, and
+    /// with `whole_word_unk` set, a single piece failing to match
+    /// marks the *entire* word `Unk` rather than just its remainder.
+    ///
+    /// With a default `SplitConfig`, `split_with` behaves exactly
+    /// like `split`.
+    pub fn split_with<'b>(&self, word: &'b str, config: &SplitConfig) -> WordPieceConfigIter<'b> {
+        if let Some(max_chars) = config.max_input_chars_per_word {
+            if word.chars().count() > max_chars {
+                return WordPieceConfigIter(vec![Self::unk_or_missing(config)].into_iter());
+            }
+        }
+
+        let pieces: Vec<_> = self.split(word).collect();
+
+        if config.whole_word_unk && pieces.contains(&WordPiece::Missing) {
+            return WordPieceConfigIter(vec![Self::unk_or_missing(config)].into_iter());
+        }
+
+        WordPieceConfigIter(pieces.into_iter())
+    }
+
+    /// The piece to emit for an out-of-vocabulary word: `Unk` when
+    /// `config` has an `unk_idx` configured, `Missing` otherwise.
+    fn unk_or_missing<'b>(config: &SplitConfig) -> WordPiece<'b> {
+        match config.unk_idx {
+            Some(idx) => WordPiece::Unk { idx },
+            None => WordPiece::Missing,
+        }
+    }
+
+    /// Tokenize a full text into word pieces.
+    ///
+    /// `split` (and its variants) operate on a single, already
+    /// segmented word. `tokenize` instead takes an entire `text`,
+    /// pre-segments it on basic Unicode whitespace and punctuation
+    /// boundaries -- runs of alphanumeric characters form a segment,
+    /// and every other non-whitespace character is a segment of its
+    /// own -- and applies [`split`](WordPieces::split) to each
+    /// segment in turn.
+    ///
+    /// Each yielded [`Token`] carries the [`WordPiece`] along with
+    /// the byte range in `text` that it spans, computed by
+    /// accumulating the consumed length within each segment relative
+    /// to the segment's start. This lets callers (e.g. NER or
+    /// highlighting code) map word pieces back to their source span.
+    ///
+    /// Unlike `split`, `tokenize` does not panic on input with no
+    /// segments, such as an empty or all-whitespace `text`; it simply
+    /// yields no tokens.
+    pub fn tokenize<'a, 'b>(&'a self, text: &'b str) -> TokenIter<'a, 'b, D> {
+        TokenIter {
+            word_pieces: self,
+            text,
+            offset: 0,
+            current: None,
+        }
+    }
+
+    /// Find every piece that starts with `prefix`.
+    ///
+    /// Returns the word-initial pieces that begin with `prefix`. If
+    /// `include_continuation` is `true`, continuation pieces that
+    /// begin with `prefix` are included as well, with their `##`
+    /// marker restored. This mirrors BIP-39-style word completion,
+    /// where typing a prefix narrows down the set of vocabulary
+    /// entries it could complete to.
+    pub fn completions(
+        &self,
+        prefix: &str,
+        include_continuation: bool,
+    ) -> impl Iterator<Item = (String, u64)> {
+        let automaton = Str::new(prefix).starts_with();
+
+        let mut completions = Self::matching_pieces(&self.word_initial, &automaton, "");
+        if include_continuation {
+            completions.extend(Self::matching_pieces(&self.continuation, &automaton, "##"));
+        }
+
+        completions.into_iter()
+    }
+
+    /// Collect every piece in `piece_map` accepted by `automaton`,
+    /// prepending `marker` (e.g. `"##"` for continuation pieces) to
+    /// each match.
+    fn matching_pieces<A>(piece_map: &Map<D>, automaton: A, marker: &str) -> Vec<(String, u64)>
+    where
+        A: Automaton,
+    {
+        let mut matches = Vec::new();
+
+        let mut stream = piece_map.search(automaton).into_stream();
+        while let Some((piece, idx)) = stream.next() {
+            if let Ok(piece) = std::str::from_utf8(piece) {
+                matches.push((format!("{}{}", marker, piece), idx));
+            }
+        }
+
+        matches
+    }
+
+    /// Look up whether `prefix` is a complete piece, along with the
+    /// characters that could extend it towards one.
+    ///
+    /// `prefix` is looked up among the word-initial pieces by
+    /// walking the raw FST node by node, the same way
+    /// `longest_prefix_len` does, then inspecting the outgoing
+    /// transitions of the node reached at the end of `prefix` to
+    /// find the characters that could follow.
+    pub fn completion_mask(&self, prefix: &str) -> CompletionInfo {
+        let fst = self.word_initial.as_fst();
+
+        let mut node = fst.root();
+        for &byte in prefix.as_bytes() {
+            match node.find_input(byte) {
+                Some(trans_idx) => node = fst.node(node.transition(trans_idx).addr),
+                None => {
+                    return CompletionInfo {
+                        is_piece: false,
+                        next_chars: Vec::new(),
+                    }
+                }
+            }
+        }
+
+        CompletionInfo {
+            is_piece: node.is_final(),
+            next_chars: Self::next_chars(fst, &node),
+        }
+    }
+
+    /// Decode the complete `char`s that could follow `node`, possibly
+    /// spanning multiple (multi-byte) transitions.
+    fn next_chars(fst: &Fst<D>, node: &Node) -> Vec<char> {
+        let mut chars = Vec::new();
+
+        for trans in node.transitions() {
+            let needed_len = utf8_char_len(trans.inp);
+            let mut bytes = vec![trans.inp];
+            Self::collect_utf8_chars(fst, fst.node(trans.addr), &mut bytes, needed_len, &mut chars);
+        }
+
+        chars
+    }
+
+    /// Depth-first search for complete UTF-8 byte sequences of
+    /// `needed_len` bytes, starting from `bytes` and continuing along
+    /// `node`'s transitions.
+    fn collect_utf8_chars(
+        fst: &Fst<D>,
+        node: Node,
+        bytes: &mut Vec<u8>,
+        needed_len: usize,
+        chars: &mut Vec<char>,
+    ) {
+        if bytes.len() == needed_len {
+            if let Ok(Some(c)) = std::str::from_utf8(bytes).map(|s| s.chars().next()) {
+                if !chars.contains(&c) {
+                    chars.push(c);
+                }
+            }
+            return;
+        }
+
+        for trans in node.transitions() {
+            bytes.push(trans.inp);
+            Self::collect_utf8_chars(fst, fst.node(trans.addr), bytes, needed_len, chars);
+            bytes.pop();
+        }
+    }
+
+    /// Find the closest piece in `piece_map` to a prefix of `word`,
+    /// within `max_distance` edits.
+    ///
+    /// Since pieces are usually much shorter than an entire
+    /// misspelled word, we cannot just build one Levenshtein
+    /// automaton for the whole of `word`: a short piece would never
+    /// be "close enough" to a much longer string. Instead, we try
+    /// successive prefixes of `word`, from shortest to longest,
+    /// build a Levenshtein automaton for each, and intersect it with
+    /// `piece_map` (`Map::search`) to collect pieces within
+    /// `max_distance` edits of that prefix. Among all candidates
+    /// found this way, the longest piece wins, with ties broken by
+    /// the smallest edit distance, and then by the shortest prefix.
+    fn fuzzy_longest_prefix_len(
+        piece_map: &Map<D>,
+        word: &str,
+        max_distance: u32,
+    ) -> Option<(String, u64, u32, usize)> {
+        let mut best: Option<(String, u64, u32, usize)> = None;
+
+        // Every codepoint boundary after the start of `word`, i.e.
+        // the end of each successive prefix.
+        let prefix_ends = word
+            .char_indices()
+            .skip(1)
+            .map(|(idx, _)| idx)
+            .chain([word.len()]);
+
+        for prefix_len in prefix_ends {
+            let prefix = &word[..prefix_len];
+
+            // A Levenshtein automaton has a combined limit on
+            // `max_distance` and the query length; skip prefix
+            // lengths that exceed it instead of failing the whole
+            // search.
+            let automaton = match Levenshtein::new(prefix, max_distance) {
+                Ok(automaton) => automaton,
+                Err(_) => continue,
+            };
+
+            let mut stream = piece_map.search(&automaton).into_stream();
+            while let Some((piece, idx)) = stream.next() {
+                let piece = match std::str::from_utf8(piece) {
+                    Ok(piece) => piece,
+                    Err(_) => continue,
+                };
+
+                // `piece` was matched against `prefix`, i.e. the
+                // first `prefix_len` bytes of `word`, not against
+                // `piece.len()` bytes of it -- those only happen to
+                // coincide for same-length substitution typos. Using
+                // `piece.len()` here would compute the distance
+                // against a misaligned slice and consume the wrong
+                // number of input bytes for insertion/deletion typos.
+                let distance = levenshtein_distance(piece, prefix);
+                if distance > max_distance {
+                    continue;
+                }
+
+                let is_better = match &best {
+                    Some((best_piece, _, best_distance, _)) => {
+                        piece.len() > best_piece.len()
+                            || (piece.len() == best_piece.len() && distance < *best_distance)
+                    }
+                    None => true,
+                };
+
+                if is_better {
+                    best = Some((piece.to_string(), idx, distance, prefix_len));
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Serialize these word pieces to `writer`.
+    ///
+    /// The format is a small header (magic bytes and a format
+    /// version), followed by the word-initial and continuation
+    /// `fst::Map`s, each prefixed with its length as a little-endian
+    /// `u64`. The result can be loaded back, without recompiling the
+    /// FSTs, using [`from_bytes`](WordPieces::from_bytes) or
+    /// [`from_mmap`](WordPieces::from_mmap).
+    pub fn write<W>(&self, mut writer: W) -> Result<(), WordPiecesError>
+    where
+        W: Write,
+    {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+
+        Self::write_section(&mut writer, self.word_initial.as_fst().as_bytes())?;
+        Self::write_section(&mut writer, self.continuation.as_fst().as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Write a single length-prefixed section.
+    fn write_section<W>(writer: &mut W, bytes: &[u8]) -> Result<(), WordPiecesError>
+    where
+        W: Write,
+    {
+        writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        writer.write_all(bytes)?;
+
+        Ok(())
+    }
+}
+
+impl WordPieces<Vec<u8>> {
+    /// Construct word pieces by compiling the word pieces listed in
+    /// `buf_read`, one piece per line.
+    pub fn from_buf_read(buf_read: impl BufRead) -> Result<Self, WordPiecesError> {
+        let mut builder = WordPiecesBuilder::new();
+
+        for (idx, piece) in buf_read.lines().enumerate() {
+            let piece = piece?;
+            builder.insert(&piece, idx as u64);
+        }
+
+        builder.build()
+    }
+}
+
+impl<'d> WordPieces<&'d [u8]> {
+    /// Construct word pieces from bytes written by
+    /// [`write`](WordPieces::write), without copying or recompiling
+    /// the underlying FSTs.
+    ///
+    /// This is a zero-copy counterpart to
+    /// [`from_buf_read`](WordPieces::from_buf_read): `data` can be a
+    /// memory-mapped file, and the returned `WordPieces` borrows from
+    /// it directly. See [`from_mmap`](WordPieces::from_mmap) for a
+    /// convenience constructor that owns the mapping.
+    pub fn from_bytes(data: &'d [u8]) -> Result<Self, WordPiecesError> {
+        let (word_initial, continuation) = parse_sections(data)?;
+
+        Ok(WordPieces {
+            word_initial: Map::new(&data[word_initial])?,
+            continuation: Map::new(&data[continuation])?,
+        })
+    }
+}
+
+/// Parse the header written by [`WordPieces::write`] and return the
+/// byte ranges of the word-initial and continuation sections.
+fn parse_sections(data: &[u8]) -> Result<(Range<usize>, Range<usize>), WordPiecesError> {
+    let header_len = MAGIC.len() + 4;
+    if data.len() < header_len {
+        return Err(WordPiecesError::InvalidFormat(
+            "truncated header".to_string(),
+        ));
+    }
+
+    if &data[..MAGIC.len()] != MAGIC {
+        return Err(WordPiecesError::InvalidFormat(
+            "not a word pieces file".to_string(),
+        ));
+    }
+
+    let version = u32::from_le_bytes(data[MAGIC.len()..header_len].try_into().unwrap());
+    if version != FORMAT_VERSION {
+        return Err(WordPiecesError::InvalidFormat(format!(
+            "unsupported format version: {version}"
+        )));
+    }
+
+    let (word_initial, offset) = read_section(data, header_len)?;
+    let (continuation, _) = read_section(data, offset)?;
+
+    Ok((word_initial, continuation))
+}
+
+/// Read a single length-prefixed section starting at `offset`,
+/// returning its byte range and the offset of the following section.
+fn read_section(data: &[u8], offset: usize) -> Result<(Range<usize>, usize), WordPiecesError> {
+    let len_bytes = data
+        .get(offset..offset + 8)
+        .ok_or_else(|| WordPiecesError::InvalidFormat("truncated section length".to_string()))?;
+    let len = u64::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+
+    let start = offset + 8;
+    let end = start
+        .checked_add(len)
+        .filter(|&end| end <= data.len())
+        .ok_or_else(|| WordPiecesError::InvalidFormat("truncated section".to_string()))?;
+
+    Ok((start..end, end))
+}
+
+/// A byte range within a shared, reference-counted memory-mapped
+/// buffer, used as the backing storage of a [`WordPieces`] loaded by
+/// [`WordPieces::from_mmap`].
+#[cfg(feature = "mmap")]
+#[derive(Clone)]
+pub struct MmapSection {
+    mmap: std::sync::Arc<memmap2::Mmap>,
+    range: Range<usize>,
+}
+
+#[cfg(feature = "mmap")]
+impl AsRef<[u8]> for MmapSection {
+    fn as_ref(&self) -> &[u8] {
+        &self.mmap[self.range.clone()]
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl WordPieces<MmapSection> {
+    /// Construct word pieces from a memory-mapped file written by
+    /// [`write`](WordPieces::write).
+    ///
+    /// Unlike [`from_bytes`](WordPieces::from_bytes), the returned
+    /// `WordPieces` owns `mmap`, so it is not tied to a borrow of it.
+    pub fn from_mmap(mmap: memmap2::Mmap) -> Result<Self, WordPiecesError> {
+        let (word_initial, continuation) = parse_sections(&mmap)?;
+
+        let mmap = std::sync::Arc::new(mmap);
+
+        Ok(WordPieces {
+            word_initial: Map::new(MmapSection {
+                mmap: mmap.clone(),
+                range: word_initial,
+            })?,
+            continuation: Map::new(MmapSection {
+                mmap,
+                range: continuation,
+            })?,
+        })
+    }
+}
+
+/// The result of [`WordPieces::completion_mask`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CompletionInfo {
+    is_piece: bool,
+    next_chars: Vec<char>,
+}
+
+impl CompletionInfo {
+    /// Whether the queried prefix is itself a complete word piece.
+    pub fn is_piece(&self) -> bool {
+        self.is_piece
+    }
+
+    /// The characters that could follow the queried prefix towards a
+    /// valid word piece.
+    pub fn next_chars(&self) -> &[char] {
+        &self.next_chars
+    }
+}
+
+/// A word piece together with the byte range in the source text that
+/// it spans.
+///
+/// Returned by [`WordPieces::tokenize`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct Token<'a> {
+    piece: WordPiece<'a>,
+    span: Range<usize>,
 }
 
-impl From<&WordPieces> for Vec<String> {
-    fn from(word_pieces: &WordPieces) -> Self {
+impl<'a> Token<'a> {
+    /// The word piece.
+    pub fn piece(&self) -> &WordPiece<'a> {
+        &self.piece
+    }
+
+    /// The byte range in the source text that this piece spans.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+/// Configuration for [`WordPieces::split_with`].
+///
+/// The default configuration matches the behavior of
+/// [`split`](WordPieces::split): no length limit and no whole-word
+/// `[UNK]` handling.
+#[derive(Debug, Clone, Default)]
+pub struct SplitConfig {
+    unk_idx: Option<u64>,
+    max_input_chars_per_word: Option<usize>,
+    whole_word_unk: bool,
+}
+
+impl SplitConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Vocabulary index to use for out-of-vocabulary words.
+    ///
+    /// Without an `unk_idx`, out-of-vocabulary words are reported as
+    /// `WordPiece::Missing` rather than `WordPiece::Unk`.
+    pub fn unk_idx(mut self, unk_idx: u64) -> Self {
+        self.unk_idx = Some(unk_idx);
+        self
+    }
+
+    /// Reject words with more than `max_input_chars_per_word`
+    /// characters as out-of-vocabulary, without attempting to split
+    /// them.
+    pub fn max_input_chars_per_word(mut self, max_input_chars_per_word: usize) -> Self {
+        self.max_input_chars_per_word = Some(max_input_chars_per_word);
+        self
+    }
+
+    /// If `true`, a word for which any piece fails to match is
+    /// reported as out-of-vocabulary in its entirety, mirroring the
+    /// original BERT WordPiece algorithm. If `false` (the default),
+    /// the pieces that were found are reported, followed by a single
+    /// `WordPiece::Missing` for the unmatched remainder.
+    pub fn whole_word_unk(mut self, whole_word_unk: bool) -> Self {
+        self.whole_word_unk = whole_word_unk;
+        self
+    }
+}
+
+/// The number of bytes in the UTF-8 encoding of a character, given
+/// its leading byte.
+fn utf8_char_len(first_byte: u8) -> usize {
+    if first_byte & 0x80 == 0x00 {
+        1
+    } else if first_byte & 0xE0 == 0xC0 {
+        2
+    } else if first_byte & 0xF0 == 0xE0 {
+        3
+    } else {
+        4
+    }
+}
+
+/// Find the byte range of the next pre-tokenization segment in
+/// `text` at or after `offset`.
+///
+/// A run of alphanumeric characters forms a single segment; every
+/// other non-whitespace character is a segment of its own. Whitespace
+/// is skipped and never produces a segment. Returns `None` once
+/// `text[offset..]` contains nothing but whitespace (including when
+/// it is empty).
+fn next_segment(text: &str, offset: usize) -> Option<Range<usize>> {
+    let mut chars = text[offset..].char_indices();
+
+    let (start_rel, first) = loop {
+        match chars.next() {
+            Some((_, c)) if c.is_whitespace() => continue,
+            Some(found) => break found,
+            None => return None,
+        }
+    };
+
+    let start = offset + start_rel;
+
+    if !first.is_alphanumeric() {
+        return Some(start..start + first.len_utf8());
+    }
+
+    let mut end = start + first.len_utf8();
+    for (idx, c) in chars {
+        if !c.is_alphanumeric() {
+            break;
+        }
+        end = offset + idx + c.len_utf8();
+    }
+
+    Some(start..end)
+}
+
+/// Compute the Levenshtein (edit) distance between two strings,
+/// operating on `char`s rather than bytes.
+fn levenshtein_distance(a: &str, b: &str) -> u32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev_row: Vec<u32> = (0..=b.len() as u32).collect();
+    let mut curr_row = vec![0u32; b.len() + 1];
+
+    for (i, &a_char) in a.iter().enumerate() {
+        curr_row[0] = i as u32 + 1;
+
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution_cost = if a_char == b_char { 0 } else { 1 };
+            curr_row[j + 1] = (prev_row[j + 1] + 1)
+                .min(curr_row[j] + 1)
+                .min(prev_row[j] + substitution_cost);
+        }
+
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    prev_row[b.len()]
+}
+
+impl<D> From<&WordPieces<D>> for Vec<String>
+where
+    D: AsRef<[u8]>,
+{
+    fn from(word_pieces: &WordPieces<D>) -> Self {
         let mut pieces =
             vec![String::new(); word_pieces.word_initial.len() + word_pieces.continuation.len()];
 
@@ -159,8 +772,26 @@ pub enum WordPiece<'a> {
     /// The next found word piece.
     Found { piece: &'a str, idx: u64 },
 
+    /// A word piece that was not an exact match, but was repaired to
+    /// the closest piece within some edit distance by
+    /// [`split_fuzzy`](WordPieces::split_fuzzy).
+    ///
+    /// `piece` is the matched vocabulary entry, not the (possibly
+    /// misspelled) input text, so it is owned rather than borrowed
+    /// from the input word.
+    Corrected {
+        piece: String,
+        idx: u64,
+        distance: u32,
+    },
+
     /// No piece was found for the (remaining part of) the word.
     Missing,
+
+    /// The entire word was marked out-of-vocabulary by
+    /// [`split_with`](WordPieces::split_with), rather than just the
+    /// (remaining part of the) word that failed to match.
+    Unk { idx: u64 },
 }
 
 impl<'a> WordPiece<'a> {
@@ -168,28 +799,36 @@ impl<'a> WordPiece<'a> {
     pub fn idx(&self) -> Option<u64> {
         match self {
             WordPiece::Found { idx, .. } => Some(*idx),
+            WordPiece::Corrected { idx, .. } => Some(*idx),
             WordPiece::Missing => None,
+            WordPiece::Unk { idx } => Some(*idx),
         }
     }
 
     /// Unwrap a piece if present.
-    pub fn piece(&self) -> Option<&'a str> {
+    ///
+    /// The returned reference borrows from `self` rather than from
+    /// `'a`, since a [`Corrected`](WordPiece::Corrected) piece owns
+    /// its string.
+    pub fn piece(&self) -> Option<&str> {
         match self {
             WordPiece::Found { piece, .. } => Some(piece),
+            WordPiece::Corrected { piece, .. } => Some(piece),
             WordPiece::Missing => None,
+            WordPiece::Unk { .. } => None,
         }
     }
 }
 
-impl<'a> From<&WordPiece<'a>> for Option<&'a str> {
-    fn from(word_piece: &WordPiece<'a>) -> Self {
+impl<'q, 'a> From<&'q WordPiece<'a>> for Option<&'q str> {
+    fn from(word_piece: &'q WordPiece<'a>) -> Self {
         word_piece.piece()
     }
 }
 
 /// Iterator over word pieces.
-pub struct WordPieceIter<'a, 'b> {
-    word_pieces: &'a WordPieces,
+pub struct WordPieceIter<'a, 'b, D> {
+    word_pieces: &'a WordPieces<D>,
 
     /// The remaining word.
     word: &'b str,
@@ -198,7 +837,10 @@ pub struct WordPieceIter<'a, 'b> {
     initial: bool,
 }
 
-impl<'a, 'b> Iterator for WordPieceIter<'a, 'b> {
+impl<'a, 'b, D> Iterator for WordPieceIter<'a, 'b, D>
+where
+    D: AsRef<[u8]>,
+{
     type Item = WordPiece<'b>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -237,6 +879,147 @@ impl<'a, 'b> Iterator for WordPieceIter<'a, 'b> {
     }
 }
 
+/// Iterator over word pieces, with a typo-tolerant fallback.
+///
+/// Returned by [`WordPieces::split_fuzzy`].
+pub struct WordPieceFuzzyIter<'a, 'b, D> {
+    word_pieces: &'a WordPieces<D>,
+
+    /// The remaining word.
+    word: &'b str,
+
+    /// Is this the initial word piece?
+    initial: bool,
+
+    /// Maximum edit distance for the fuzzy fallback.
+    max_distance: u32,
+}
+
+impl<'a, 'b, D> Iterator for WordPieceFuzzyIter<'a, 'b, D>
+where
+    D: AsRef<[u8]>,
+{
+    type Item = WordPiece<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.word.is_empty() {
+            assert!(
+                !self.initial,
+                "Cannot break an empty string into word pieces"
+            );
+            return None;
+        }
+
+        // Pick the word-initial or continuation set.
+        let set = if self.initial {
+            self.initial = false;
+            &self.word_pieces.word_initial
+        } else {
+            &self.word_pieces.continuation
+        };
+
+        // First, try an exact greedy longest-match, as `split` does.
+        let (prefix_len, prefix_idx) = WordPieces::longest_prefix_len(set, self.word);
+        if prefix_len > 0 {
+            let piece = &self.word[..prefix_len];
+            self.word = &self.word[prefix_len..];
+            return Some(WordPiece::Found {
+                piece,
+                idx: prefix_idx,
+            });
+        }
+
+        // Fall back to the closest piece within `max_distance` edits.
+        match WordPieces::fuzzy_longest_prefix_len(set, self.word, self.max_distance) {
+            Some((piece, idx, distance, prefix_len)) => {
+                // Consume the prefix that was actually matched
+                // against the automaton, not `piece.len()` bytes --
+                // those only coincide for same-length substitution
+                // typos.
+                self.word = &self.word[prefix_len..];
+                Some(WordPiece::Corrected {
+                    piece,
+                    idx,
+                    distance,
+                })
+            }
+            None => {
+                // No matching or close-enough piece, empty the word.
+                self.word = &self.word[self.word.len()..];
+                Some(WordPiece::Missing)
+            }
+        }
+    }
+}
+
+/// Iterator over word pieces, following a [`SplitConfig`].
+///
+/// Returned by [`WordPieces::split_with`].
+pub struct WordPieceConfigIter<'b>(std::vec::IntoIter<WordPiece<'b>>);
+
+impl<'b> Iterator for WordPieceConfigIter<'b> {
+    type Item = WordPiece<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Iterator over [`Token`]s.
+///
+/// Returned by [`WordPieces::tokenize`].
+pub struct TokenIter<'a, 'b, D> {
+    word_pieces: &'a WordPieces<D>,
+
+    /// The text being tokenized.
+    text: &'b str,
+
+    /// Byte offset in `text` from which pre-tokenization should
+    /// continue once the current segment is exhausted.
+    offset: usize,
+
+    /// The word-piece iterator for the current segment, along with
+    /// the byte offset in `text` where that segment ends.
+    current: Option<(WordPieceIter<'a, 'b, D>, usize)>,
+}
+
+impl<'a, 'b, D> Iterator for TokenIter<'a, 'b, D>
+where
+    D: AsRef<[u8]>,
+{
+    type Item = Token<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((inner, segment_end)) = &mut self.current {
+                let remaining_before = inner.word.len();
+
+                match inner.next() {
+                    Some(piece) => {
+                        let remaining_after = inner.word.len();
+                        let start = *segment_end - remaining_before;
+                        let end = *segment_end - remaining_after;
+                        return Some(Token {
+                            piece,
+                            span: start..end,
+                        });
+                    }
+                    None => self.current = None,
+                }
+
+                continue;
+            }
+
+            let segment = next_segment(self.text, self.offset)?;
+            self.offset = segment.end;
+            self.current = Some((
+                self.word_pieces.split(&self.text[segment.clone()]),
+                segment.end,
+            ));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
@@ -246,9 +1029,9 @@ mod tests {
 
     use fst::{Map, MapBuilder};
 
-    use crate::WordPiecesBuilder;
+    use crate::word_pieces::WordPiecesBuilder;
 
-    use super::{WordPiece, WordPieces};
+    use super::{SplitConfig, WordPiece, WordPieces};
 
     fn pieces_to_map(pieces: &[(&str, u64)]) -> Map<Vec<u8>> {
         let pieces =
@@ -351,6 +1134,227 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fuzzy_split_corrects_typo() {
+        let word_pieces = example_word_pieces();
+
+        // "voar" is a one-edit typo of "voor", and the corrected
+        // piece should be the vocabulary entry "voor", not the typo
+        // itself.
+        assert_eq!(
+            word_pieces.split_fuzzy("voarkomen", 1).collect::<Vec<_>>(),
+            vec![
+                WordPiece::Corrected {
+                    piece: "voor".to_string(),
+                    idx: 0,
+                    distance: 1
+                },
+                WordPiece::Found {
+                    piece: "kom",
+                    idx: 3
+                },
+                WordPiece::Found {
+                    piece: "en",
+                    idx: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_split_corrects_deletion_typo() {
+        let word_pieces = example_word_pieces();
+
+        // "vorkomen" is missing an "o" from "voorkomen" (a deletion
+        // typo), so the matched prefix is shorter than the matched
+        // piece. Distance and consumed input bytes must be tracked
+        // against the matched prefix, not against `piece.len()` bytes
+        // of the input, or this falls back to `Missing`.
+        assert_eq!(
+            word_pieces.split_fuzzy("vorkomen", 1).collect::<Vec<_>>(),
+            vec![
+                WordPiece::Corrected {
+                    piece: "voor".to_string(),
+                    idx: 0,
+                    distance: 1
+                },
+                WordPiece::Found {
+                    piece: "kom",
+                    idx: 3
+                },
+                WordPiece::Found {
+                    piece: "en",
+                    idx: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_split_corrects_insertion_typo() {
+        let word_pieces = example_word_pieces();
+
+        // "voorrkomen" has an extra "r" inserted before "komen" (an
+        // insertion typo), so the matched prefix is longer than the
+        // matched piece.
+        assert_eq!(
+            word_pieces.split_fuzzy("voorrkomen", 1).collect::<Vec<_>>(),
+            vec![
+                WordPiece::Found {
+                    piece: "voor",
+                    idx: 0
+                },
+                WordPiece::Corrected {
+                    piece: "kom".to_string(),
+                    idx: 3,
+                    distance: 1
+                },
+                WordPiece::Found {
+                    piece: "en",
+                    idx: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzy_split_does_not_panic_on_long_input() {
+        let word_pieces = example_word_pieces();
+
+        // A long, entirely non-matching word used to panic by
+        // exceeding the Levenshtein automaton's internal state limit,
+        // even for a small `max_distance`. It should fall back to
+        // `Missing` instead. A word with varied characters hits the
+        // state limit at a much shorter length than a repeated
+        // character would, keeping this test fast.
+        let long_word: String = "abcdefghijklmnopqrstuvwxyz".chars().cycle().take(60).collect();
+        assert_eq!(
+            word_pieces.split_fuzzy(&long_word, 2).collect::<Vec<_>>(),
+            vec![WordPiece::Missing]
+        );
+    }
+
+    #[test]
+    fn fuzzy_split_falls_back_to_missing() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces.split_fuzzy("xyz", 1).collect::<Vec<_>>(),
+            vec![WordPiece::Missing]
+        );
+    }
+
+    #[test]
+    fn completions_word_initial_only() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces.completions("vo", false).collect::<Vec<_>>(),
+            vec![("voor".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn completions_with_continuation() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces.completions("ko", true).collect::<Vec<_>>(),
+            vec![("##kom".to_string(), 3), ("##komt".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn completion_mask_in_progress_prefix() {
+        let word_pieces = example_word_pieces();
+
+        let mask = word_pieces.completion_mask("vo");
+        assert!(!mask.is_piece());
+        assert_eq!(mask.next_chars(), &['o']);
+    }
+
+    #[test]
+    fn completion_mask_complete_piece() {
+        let word_pieces = example_word_pieces();
+
+        let mask = word_pieces.completion_mask("voor");
+        assert!(mask.is_piece());
+        assert_eq!(mask.next_chars(), &[]);
+    }
+
+    #[test]
+    fn completion_mask_unknown_prefix() {
+        let word_pieces = example_word_pieces();
+
+        let mask = word_pieces.completion_mask("xyz");
+        assert!(!mask.is_piece());
+        assert_eq!(mask.next_chars(), &[]);
+    }
+
+    #[test]
+    fn split_with_default_config_matches_split() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces
+                .split_with("voorkomen", &SplitConfig::new())
+                .collect::<Vec<_>>(),
+            word_pieces.split("voorkomen").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn split_with_whole_word_unk() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces
+                .split_with(
+                    "voorman",
+                    &SplitConfig::new().whole_word_unk(true).unk_idx(99)
+                )
+                .collect::<Vec<_>>(),
+            vec![WordPiece::Unk { idx: 99 }]
+        );
+
+        // A word that can be split fully is unaffected.
+        assert_eq!(
+            word_pieces
+                .split_with("voorkomen", &SplitConfig::new().whole_word_unk(true))
+                .collect::<Vec<_>>(),
+            word_pieces.split("voorkomen").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn split_with_whole_word_unk_without_unk_idx() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces
+                .split_with("voorman", &SplitConfig::new().whole_word_unk(true))
+                .collect::<Vec<_>>(),
+            vec![WordPiece::Missing]
+        );
+    }
+
+    #[test]
+    fn split_with_max_input_chars_per_word() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(
+            word_pieces
+                .split_with(
+                    "voorkomen",
+                    &SplitConfig::new()
+                        .max_input_chars_per_word(4)
+                        .unk_idx(99)
+                )
+                .collect::<Vec<_>>(),
+            vec![WordPiece::Unk { idx: 99 }]
+        );
+    }
+
     #[test]
     fn test_original_pieces_are_returned() {
         let f = File::open("testdata/test.pieces").unwrap();
@@ -406,4 +1410,77 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn write_and_from_bytes_roundtrip() {
+        let word_pieces = example_word_pieces();
+
+        let mut serialized = Vec::new();
+        word_pieces.write(&mut serialized).unwrap();
+
+        let loaded = WordPieces::from_bytes(&serialized).unwrap();
+
+        assert_eq!(
+            loaded.split("voorkomen").collect::<Vec<_>>(),
+            word_pieces.split("voorkomen").collect::<Vec<_>>()
+        );
+        assert_eq!(Vec::from(&loaded), Vec::from(&word_pieces));
+    }
+
+    #[test]
+    fn from_bytes_rejects_bad_magic() {
+        assert!(matches!(
+            WordPieces::from_bytes(b"not a word pieces file"),
+            Err(crate::WordPiecesError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn from_bytes_rejects_truncated_data() {
+        let word_pieces = example_word_pieces();
+
+        let mut serialized = Vec::new();
+        word_pieces.write(&mut serialized).unwrap();
+        serialized.truncate(serialized.len() - 1);
+
+        assert!(matches!(
+            WordPieces::from_bytes(&serialized),
+            Err(crate::WordPiecesError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn tokenize_splits_on_whitespace_and_punctuation_with_spans() {
+        let word_pieces = example_word_pieces();
+
+        let tokens = word_pieces
+            .tokenize("voorkomen, voor")
+            .map(|token| {
+                (
+                    token.piece().piece().map(|piece| piece.to_string()),
+                    token.piece().idx(),
+                    token.span(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Some("voor".to_string()), Some(0), 0..4),
+                (Some("kom".to_string()), Some(3), 4..7),
+                (Some("en".to_string()), Some(4), 7..9),
+                (None, None, 9..10),
+                (Some("voor".to_string()), Some(0), 11..15),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_empty_or_whitespace_only_yields_no_tokens() {
+        let word_pieces = example_word_pieces();
+
+        assert_eq!(word_pieces.tokenize("").collect::<Vec<_>>(), vec![]);
+        assert_eq!(word_pieces.tokenize("   ").collect::<Vec<_>>(), vec![]);
+    }
 }